@@ -7,6 +7,7 @@ use crate::types::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ffi::CString;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 // Simple base64 decoding (standard alphabet)
 fn base64_decode(input: &str) -> Result<Vec<u8>> {
@@ -55,10 +56,465 @@ fn base64_decode(input: &str) -> Result<Vec<u8>> {
     Ok(output)
 }
 
+/// Map a WHATWG charset label to the canonical name of a decoder we support.
+///
+/// Only UTF-8 and the single-byte encodings in common use are implemented
+/// here (`utf-8`, `us-ascii`, `iso-8859-1`/`latin1`, `windows-1252`).
+/// Multi-byte legacy encodings such as `gbk` or `shift_jis` aren't
+/// implemented in this WASM guest; unrecognized labels fall back to UTF-8.
+fn normalize_charset_label(label: &str) -> &'static str {
+    match label.trim().to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" | "unicode-1-1-utf-8" => "utf-8",
+        "us-ascii" | "ascii" | "iso-ir-6" | "ansi_x3.4-1968" => "utf-8",
+        "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "l1" | "8859-1" | "cp819" => {
+            "iso-8859-1"
+        }
+        "windows-1252" | "cp1252" | "x-cp1252" => "windows-1252",
+        _ => "utf-8",
+    }
+}
+
+/// Every byte maps directly to the identical Unicode code point in Latin-1.
+fn decode_iso_8859_1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Latin-1 with the C1 control range (0x80-0x9F) remapped to the printable
+/// characters Windows actually puts there.
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}',
+        '\u{017D}', '\u{FFFD}', '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}',
+        '\u{2022}', '\u{2013}', '\u{2014}', '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}',
+        '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+    ];
+
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+/// Decode `bytes` as `label`, erroring if the label maps to UTF-8 and the
+/// bytes aren't valid UTF-8.
+fn decode_charset(bytes: &[u8], label: &str) -> Result<String> {
+    match normalize_charset_label(label) {
+        "iso-8859-1" => Ok(decode_iso_8859_1(bytes)),
+        "windows-1252" => Ok(decode_windows_1252(bytes)),
+        _ => String::from_utf8(bytes.to_vec())
+            .map_err(|e| Error::Other(format!("invalid UTF-8 in response body: {}", e))),
+    }
+}
+
+/// Like [`decode_charset`], but replaces invalid sequences instead of erroring.
+fn decode_charset_lossy(bytes: &[u8], label: &str) -> String {
+    match normalize_charset_label(label) {
+        "iso-8859-1" => decode_iso_8859_1(bytes),
+        "windows-1252" => decode_windows_1252(bytes),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// Simple base64 encoding (standard alphabet, `=` padding)
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied();
+        let b2 = chunk.get(2).copied();
+
+        output.push(ALPHABET[(b0 >> 2) as usize] as char);
+        output.push(ALPHABET[(((b0 & 0x03) << 4) | (b1.unwrap_or(0) >> 4)) as usize] as char);
+        output.push(match b1 {
+            Some(b1) => ALPHABET[(((b1 & 0x0F) << 2) | (b2.unwrap_or(0) >> 6)) as usize] as char,
+            None => '=',
+        });
+        output.push(match b2 {
+            Some(b2) => ALPHABET[(b2 & 0x3F) as usize] as char,
+            None => '=',
+        });
+    }
+    output
+}
+
+/// Percent-encode `input`, escaping every byte outside the unreserved set
+/// `A-Za-z0-9-._~` (RFC 3986).
+fn percent_encode(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for &b in input.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                output.push(b as char);
+            }
+            _ => output.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    output
+}
+
+/// Percent-encode `pairs` as a `key=value&key=value...` query/form string.
+fn encode_pairs(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Cap decompressed size so a small, hostile response body can't expand into
+// an unbounded allocation (a classic decompression bomb). This is reachable
+// straight from an external server's response whenever the caller opted in
+// via `HttpRequest::accept_gzip`.
+const MAX_INFLATE_OUTPUT_SIZE: usize = 64 * 1024 * 1024;
+
+/// Decompress a raw DEFLATE (RFC 1951) bitstream.
+///
+/// There's no decompression crate available to this WASM guest, so this is a
+/// small from-scratch inflate supporting stored, fixed-Huffman and
+/// dynamic-Huffman blocks -- the full RFC 1951 format.
+fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    struct BitReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+        bit_buf: u32,
+        bit_count: u32,
+    }
+
+    impl<'a> BitReader<'a> {
+        fn new(data: &'a [u8]) -> Self {
+            Self {
+                data,
+                pos: 0,
+                bit_buf: 0,
+                bit_count: 0,
+            }
+        }
+
+        fn take(&mut self, n: u32) -> Result<u32> {
+            while self.bit_count < n {
+                let byte = *self
+                    .data
+                    .get(self.pos)
+                    .ok_or_else(|| Error::Other("truncated deflate stream".to_string()))?;
+                self.pos += 1;
+                self.bit_buf |= (byte as u32) << self.bit_count;
+                self.bit_count += 8;
+            }
+            let value = self.bit_buf & ((1u32 << n) - 1);
+            self.bit_buf >>= n;
+            self.bit_count -= n;
+            Ok(value)
+        }
+
+        fn align_to_byte(&mut self) {
+            self.bit_buf = 0;
+            self.bit_count = 0;
+        }
+    }
+
+    // Canonical Huffman decoding table, built per RFC 1951 section 3.2.2.
+    struct Huffman {
+        counts: [u16; 16],
+        symbols: Vec<u16>,
+    }
+
+    impl Huffman {
+        fn build(lengths: &[u8]) -> Self {
+            let mut counts = [0u16; 16];
+            for &len in lengths {
+                counts[len as usize] += 1;
+            }
+            counts[0] = 0;
+
+            let mut offsets = [0u16; 16];
+            for len in 1..16 {
+                offsets[len] = offsets[len - 1] + counts[len - 1];
+            }
+
+            let mut symbols = vec![0u16; lengths.len()];
+            for (symbol, &len) in lengths.iter().enumerate() {
+                if len != 0 {
+                    symbols[offsets[len as usize] as usize] = symbol as u16;
+                    offsets[len as usize] += 1;
+                }
+            }
+
+            Self { counts, symbols }
+        }
+
+        fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+            let mut code: i32 = 0;
+            let mut first: i32 = 0;
+            let mut index: i32 = 0;
+            for len in 1..16 {
+                code |= reader.take(1)? as i32;
+                let count = self.counts[len] as i32;
+                if code - first < count {
+                    return Ok(self.symbols[(index + (code - first)) as usize]);
+                }
+                index += count;
+                first = (first + count) << 1;
+                code <<= 1;
+            }
+            Err(Error::Other("invalid huffman code in deflate stream".to_string()))
+        }
+    }
+
+    const LENGTH_BASE: [u16; 29] = [
+        3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115,
+        131, 163, 195, 227, 258,
+    ];
+    const LENGTH_EXTRA: [u8; 29] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+    ];
+    const DIST_BASE: [u16; 30] = [
+        1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+        2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+    ];
+    const DIST_EXTRA: [u8; 30] = [
+        0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12,
+        13, 13,
+    ];
+    const CODE_LENGTH_ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    let mut reader = BitReader::new(data);
+    let mut out = Vec::new();
+
+    loop {
+        let is_final = reader.take(1)? == 1;
+        let block_type = reader.take(2)?;
+
+        match block_type {
+            0 => {
+                // Stored (uncompressed) block.
+                reader.align_to_byte();
+                if reader.pos + 4 > reader.data.len() {
+                    return Err(Error::Other("truncated stored block".to_string()));
+                }
+                let len =
+                    u16::from_le_bytes([reader.data[reader.pos], reader.data[reader.pos + 1]])
+                        as usize;
+                reader.pos += 4; // skip LEN and its one's complement
+                if reader.pos + len > reader.data.len() {
+                    return Err(Error::Other("truncated stored block".to_string()));
+                }
+                if out.len() + len > MAX_INFLATE_OUTPUT_SIZE {
+                    return Err(Error::Other("decompressed output too large".to_string()));
+                }
+                out.extend_from_slice(&reader.data[reader.pos..reader.pos + len]);
+                reader.pos += len;
+            }
+            1 | 2 => {
+                let (lit_tree, dist_tree) = if block_type == 1 {
+                    let mut lit_lengths = [0u8; 288];
+                    lit_lengths[0..144].fill(8);
+                    lit_lengths[144..256].fill(9);
+                    lit_lengths[256..280].fill(7);
+                    lit_lengths[280..288].fill(8);
+                    let dist_lengths = [5u8; 30];
+                    (Huffman::build(&lit_lengths), Huffman::build(&dist_lengths))
+                } else {
+                    let hlit = reader.take(5)? as usize + 257;
+                    let hdist = reader.take(5)? as usize + 1;
+                    let hclen = reader.take(4)? as usize + 4;
+
+                    let mut code_lengths = [0u8; 19];
+                    for i in 0..hclen {
+                        code_lengths[CODE_LENGTH_ORDER[i]] = reader.take(3)? as u8;
+                    }
+                    let code_tree = Huffman::build(&code_lengths);
+
+                    let mut lengths: Vec<u8> = Vec::with_capacity(hlit + hdist);
+                    while lengths.len() < hlit + hdist {
+                        match code_tree.decode(&mut reader)? {
+                            symbol @ 0..=15 => lengths.push(symbol as u8),
+                            16 => {
+                                let repeat = reader.take(2)? + 3;
+                                let last = *lengths.last().ok_or_else(|| {
+                                    Error::Other("invalid deflate length repeat".to_string())
+                                })?;
+                                for _ in 0..repeat {
+                                    lengths.push(last);
+                                }
+                            }
+                            17 => {
+                                let repeat = reader.take(3)? + 3;
+                                lengths.resize(lengths.len() + repeat as usize, 0);
+                            }
+                            18 => {
+                                let repeat = reader.take(7)? + 11;
+                                lengths.resize(lengths.len() + repeat as usize, 0);
+                            }
+                            _ => {
+                                return Err(Error::Other(
+                                    "invalid deflate code length symbol".to_string(),
+                                ))
+                            }
+                        }
+                    }
+
+                    (
+                        Huffman::build(&lengths[..hlit]),
+                        Huffman::build(&lengths[hlit..]),
+                    )
+                };
+
+                loop {
+                    let symbol = lit_tree.decode(&mut reader)?;
+                    if symbol < 256 {
+                        if out.len() >= MAX_INFLATE_OUTPUT_SIZE {
+                            return Err(Error::Other("decompressed output too large".to_string()));
+                        }
+                        out.push(symbol as u8);
+                    } else if symbol == 256 {
+                        break;
+                    } else {
+                        let index = (symbol - 257) as usize;
+                        if index >= LENGTH_BASE.len() {
+                            return Err(Error::Other("invalid deflate length symbol".to_string()));
+                        }
+                        let length =
+                            LENGTH_BASE[index] as usize + reader.take(LENGTH_EXTRA[index] as u32)? as usize;
+
+                        let dist_symbol = dist_tree.decode(&mut reader)? as usize;
+                        if dist_symbol >= DIST_BASE.len() {
+                            return Err(Error::Other(
+                                "invalid deflate distance symbol".to_string(),
+                            ));
+                        }
+                        let distance = DIST_BASE[dist_symbol] as usize
+                            + reader.take(DIST_EXTRA[dist_symbol] as u32)? as usize;
+                        if distance > out.len() {
+                            return Err(Error::Other(
+                                "invalid deflate back-reference".to_string(),
+                            ));
+                        }
+                        if out.len() + length > MAX_INFLATE_OUTPUT_SIZE {
+                            return Err(Error::Other("decompressed output too large".to_string()));
+                        }
+
+                        let start = out.len() - distance;
+                        for i in 0..length {
+                            out.push(out[start + i]);
+                        }
+                    }
+                }
+            }
+            _ => return Err(Error::Other("invalid deflate block type".to_string())),
+        }
+
+        if is_final {
+            break;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Strip the gzip (RFC 1952) header and CRC32/ISIZE trailer and inflate the
+/// payload.
+///
+/// All header fields are attacker-controlled (they come straight from an
+/// external server's response body), so every offset is bounds-checked and
+/// truncation is reported as an `Err` rather than indexing or slicing past
+/// the end of `data`.
+fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b {
+        return Err(Error::Other("not a gzip stream".to_string()));
+    }
+    let truncated = || Error::Other("truncated gzip header".to_string());
+
+    let flags = data[3];
+    let mut pos = 10;
+
+    if flags & 0x04 != 0 {
+        // FEXTRA
+        let extra_len = u16::from_le_bytes([
+            *data.get(pos).ok_or_else(truncated)?,
+            *data.get(pos + 1).ok_or_else(truncated)?,
+        ]) as usize;
+        pos = pos.checked_add(2 + extra_len).ok_or_else(truncated)?;
+    }
+    if flags & 0x08 != 0 {
+        // FNAME
+        loop {
+            let byte = *data.get(pos).ok_or_else(truncated)?;
+            pos += 1;
+            if byte == 0 {
+                break;
+            }
+        }
+    }
+    if flags & 0x10 != 0 {
+        // FCOMMENT
+        loop {
+            let byte = *data.get(pos).ok_or_else(truncated)?;
+            pos += 1;
+            if byte == 0 {
+                break;
+            }
+        }
+    }
+    if flags & 0x02 != 0 {
+        // FHCRC
+        pos = pos.checked_add(2).ok_or_else(truncated)?;
+    }
+
+    if pos > data.len() || data.len() - pos < 8 {
+        return Err(truncated());
+    }
+
+    inflate(&data[pos..data.len() - 8])
+}
+
+/// Strip the zlib (RFC 1950) header and Adler-32 trailer and inflate the
+/// payload.
+fn zlib_inflate(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 6 {
+        return Err(Error::Other("not a zlib stream".to_string()));
+    }
+    inflate(&data[2..data.len() - 4])
+}
+
+/// Decompress a `Content-Encoding: deflate` body, handling both the
+/// zlib-wrapped form (RFC 1950, what the HTTP spec actually describes) and
+/// the raw DEFLATE form some servers send instead.
+fn inflate_deflate(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() >= 2 && is_zlib_header(data[0], data[1]) {
+        if let Ok(out) = zlib_inflate(data) {
+            return Ok(out);
+        }
+    }
+    inflate(data)
+}
+
+/// Check the zlib (RFC 1950) header the way real implementations do: the
+/// compression method (low nibble of CMF) must be 8 (DEFLATE) and the
+/// 16-bit `CMF << 8 | FLG` must be a multiple of 31. Checking the CM
+/// nibble alone is too weak and lets raw-deflate streams whose first byte
+/// happens to match get misidentified as zlib-wrapped.
+fn is_zlib_header(cmf: u8, flg: u8) -> bool {
+    cmf & 0x0f == 8 && (u16::from(cmf) * 256 + u16::from(flg)) % 31 == 0
+}
+
 // Import host function from the "env" module
 #[link(wasm_import_module = "env")]
 extern "C" {
     fn host_http_request(request_ptr: *const u8) -> u64;
+
+    /// Pause the guest for `ms` milliseconds. WASM guests have no direct
+    /// access to a sleep syscall, so retry backoff is routed through the
+    /// host via this import.
+    fn host_sleep_ms(ms: u32);
 }
 
 /// HTTP request to be sent by the host
@@ -167,6 +623,174 @@ impl HttpRequest {
         self.timeout = seconds;
         self
     }
+
+    /// Advertise support for compressed responses by setting
+    /// `Accept-Encoding: gzip, deflate`. Combined with automatic
+    /// decompression in [`Http::request`].
+    pub fn accept_gzip(mut self) -> Self {
+        self.headers
+            .insert("Accept-Encoding".to_string(), "gzip, deflate".to_string());
+        self
+    }
+
+    /// Append percent-encoded query parameters to `url`.
+    pub fn query(mut self, pairs: &[(&str, &str)]) -> Self {
+        let query_string = encode_pairs(pairs);
+        if query_string.is_empty() {
+            return self;
+        }
+        self.url.push(if self.url.contains('?') { '&' } else { '?' });
+        self.url.push_str(&query_string);
+        self
+    }
+
+    /// Set the body to percent-encoded form fields and set
+    /// `Content-Type: application/x-www-form-urlencoded`.
+    pub fn form(mut self, pairs: &[(&str, &str)]) -> Self {
+        self.body = encode_pairs(pairs).into_bytes();
+        self.headers.insert(
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        );
+        self
+    }
+
+    /// Set an `Authorization: Basic ...` header from a username and password.
+    pub fn basic_auth(mut self, user: &str, pass: &str) -> Self {
+        let credentials = base64_encode(format!("{}:{}", user, pass).as_bytes());
+        self.headers
+            .insert("Authorization".to_string(), format!("Basic {}", credentials));
+        self
+    }
+
+    /// Set an `Authorization: Bearer ...` header from a token.
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.headers
+            .insert("Authorization".to_string(), format!("Bearer {}", token));
+        self
+    }
+
+    /// Set the body to a `multipart/form-data` encoding of `parts`, generating
+    /// a random boundary.
+    pub fn multipart(self, parts: Vec<Part>) -> Self {
+        let boundary = generate_boundary();
+        self.multipart_with_boundary(parts, &boundary)
+    }
+
+    /// Set the body to a `multipart/form-data` encoding of `parts` using a
+    /// caller-supplied boundary instead of a generated one.
+    pub fn multipart_with_boundary(mut self, parts: Vec<Part>, boundary: &str) -> Self {
+        let mut body = Vec::new();
+        for part in &parts {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            body.extend_from_slice(b"Content-Disposition: form-data; name=\"");
+            body.extend_from_slice(escape_quoted_string(&part.name).as_bytes());
+            body.extend_from_slice(b"\"");
+            if let Some(filename) = &part.filename {
+                body.extend_from_slice(
+                    format!("; filename=\"{}\"", escape_quoted_string(filename)).as_bytes(),
+                );
+            }
+            body.extend_from_slice(b"\r\n");
+            if let Some(content_type) = &part.content_type {
+                body.extend_from_slice(
+                    format!("Content-Type: {}\r\n", strip_crlf(content_type)).as_bytes(),
+                );
+            }
+            body.extend_from_slice(b"\r\n");
+            body.extend_from_slice(&part.data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        self.body = body;
+        self.headers.insert(
+            "Content-Type".to_string(),
+            format!("multipart/form-data; boundary={}", boundary),
+        );
+        self
+    }
+}
+
+/// A single part of a `multipart/form-data` body, e.g. an uploaded file or a
+/// plain form field.
+#[derive(Debug, Clone)]
+pub struct Part {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    /// Create a plain form field part.
+    pub fn new(name: &str, data: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            filename: None,
+            content_type: None,
+            data,
+        }
+    }
+
+    /// Set the part's filename, marking it as a file upload.
+    pub fn filename(mut self, filename: &str) -> Self {
+        self.filename = Some(filename.to_string());
+        self
+    }
+
+    /// Set the part's `Content-Type`.
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_string());
+        self
+    }
+}
+
+/// Escape a value for use inside a `multipart/form-data` quoted-string
+/// (RFC 7578 backslash-escapes `"` and `\`), and strip CR/LF so a hostile
+/// `name`/`filename` can't terminate the current part early and smuggle in
+/// an extra `Content-Disposition` section the receiving server would parse
+/// as a legitimately submitted field.
+fn escape_quoted_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\r' | '\n' => continue,
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Strip CR/LF from a header value so it can't inject extra header lines.
+fn strip_crlf(value: &str) -> String {
+    value.chars().filter(|&c| c != '\r' && c != '\n').collect()
+}
+
+static BOUNDARY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Generate a pseudo-random 24 hex character boundary token.
+///
+/// WASM guests have no system RNG, so this mixes a process-local counter with
+/// the counter's own address to keep boundaries distinct across calls. Use
+/// [`HttpRequest::multipart_with_boundary`] to supply your own boundary
+/// instead.
+fn generate_boundary() -> String {
+    let counter = BOUNDARY_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let seed = &BOUNDARY_COUNTER as *const AtomicU64 as u64;
+    let mut state = counter ^ seed.rotate_left(17);
+
+    let mut boundary = String::with_capacity(24);
+    for _ in 0..24 {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let nibble = ((state >> 60) & 0xF) as u32;
+        boundary.push(std::char::from_digit(nibble, 16).unwrap());
+    }
+    boundary
 }
 
 /// HTTP response from the host (internal, for JSON deserialization)
@@ -188,13 +812,57 @@ pub struct HttpResponse {
     pub headers: HashMap<String, String>,
     pub body: Vec<u8>,
     pub error: String,
+    /// Only `Some` when decompression actually changed `body` from what the
+    /// host sent; `None` means `body` already *is* the raw bytes, so
+    /// [`HttpResponse::raw_body`] falls back to it instead of storing an
+    /// identical second copy.
+    raw_body: Option<Vec<u8>>,
 }
 
 impl HttpResponse {
-    /// Get response body as string
+    /// Get response body as string, decoded using the charset declared in the
+    /// response's `Content-Type` header (falling back to UTF-8 when absent).
     pub fn text(&self) -> Result<String> {
-        String::from_utf8(self.body.clone())
-            .map_err(|e| Error::Other(format!("invalid UTF-8 in response body: {}", e)))
+        match self.charset() {
+            Some(charset) => decode_charset(&self.body, &charset),
+            None => String::from_utf8(self.body.clone())
+                .map_err(|e| Error::Other(format!("invalid UTF-8 in response body: {}", e))),
+        }
+    }
+
+    /// Like [`HttpResponse::text`], but replaces invalid sequences instead of
+    /// erroring.
+    pub fn text_lossy(&self) -> String {
+        match self.charset() {
+            Some(charset) => decode_charset_lossy(&self.body, &charset),
+            None => String::from_utf8_lossy(&self.body).into_owned(),
+        }
+    }
+
+    /// The charset label parsed out of the `charset=` parameter on the
+    /// response's `Content-Type` header, if any.
+    pub fn charset(&self) -> Option<String> {
+        let content_type = self
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Content-Type"))
+            .map(|(_, v)| v)?;
+
+        content_type.split(';').skip(1).find_map(|param| {
+            let (key, value) = param.trim().split_once('=')?;
+            if key.trim().eq_ignore_ascii_case("charset") {
+                Some(value.trim().trim_matches('"').to_string())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The response body before decompression. Identical to
+    /// [`HttpResponse::body`] unless [`HttpRequest::accept_gzip`] triggered
+    /// automatic decompression in [`Http::request`].
+    pub fn raw_body(&self) -> &[u8] {
+        self.raw_body.as_deref().unwrap_or(&self.body)
     }
 
     /// Parse response body as JSON
@@ -218,14 +886,39 @@ impl HttpResponse {
     }
 }
 
+/// Outcome of the raw host call, before the response buffer has been parsed
+/// or decoded. See [`Http::request_raw`].
+enum RawOutcome {
+    /// The host import returned its failure sentinel (`response_ptr == 0`).
+    HostFailure,
+    /// A response buffer is available at `(ptr, size)`.
+    Response(u32, u32),
+}
+
 /// Http provides HTTP request capabilities from WASM
 pub struct Http;
 
 impl Http {
     /// Perform an HTTP request
     pub fn request(req: HttpRequest) -> Result<HttpResponse> {
+        match Self::request_raw(&req)? {
+            RawOutcome::HostFailure => Err(Error::Other("HTTP request failed".to_string())),
+            RawOutcome::Response(response_ptr, response_size) => {
+                Self::build_response(&req, response_ptr, response_size)
+            }
+        }
+    }
+
+    /// Issue the host call and report only whether it produced a response or
+    /// hit the host-level failure sentinel (`response_ptr == 0`), without
+    /// parsing or decoding the body. Used by [`Http::request`] and
+    /// [`Http::request_with_retry`] so the latter can distinguish that
+    /// sentinel from the deterministic errors `build_response` can return
+    /// (malformed JSON, decompression failures, ...), which are not worth
+    /// retrying.
+    fn request_raw(req: &HttpRequest) -> Result<RawOutcome> {
         // Serialize request to JSON
-        let request_json = serde_json::to_string(&req)
+        let request_json = serde_json::to_string(req)
             .map_err(|e| Error::Other(format!("failed to serialize request: {}", e)))?;
 
         let request_c = CString::new(request_json)
@@ -239,9 +932,17 @@ impl Http {
             let response_size = ((result >> 32) & 0xFFFFFFFF) as u32;
 
             if response_ptr == 0 {
-                return Err(Error::Other("HTTP request failed".to_string()));
+                Ok(RawOutcome::HostFailure)
+            } else {
+                Ok(RawOutcome::Response(response_ptr, response_size))
             }
+        }
+    }
 
+    /// Parse and decode the host's response buffer into an [`HttpResponse`],
+    /// applying opt-in decompression per `req`.
+    fn build_response(req: &HttpRequest, response_ptr: u32, response_size: u32) -> Result<HttpResponse> {
+        unsafe {
             // Read response from memory
             let slice = std::slice::from_raw_parts(response_ptr as *const u8, response_size as usize);
             let response_json = String::from_utf8_lossy(slice);
@@ -251,14 +952,54 @@ impl Http {
                 .map_err(|e| Error::Other(format!("failed to parse response: {}", e)))?;
 
             // Decode base64 body
-            let body = base64_decode(&response_raw.body)?;
+            let raw_body = base64_decode(&response_raw.body)?;
+            let mut headers = response_raw.headers;
+
+            // Automatic decompression is opt-in: only act on Content-Encoding
+            // when the request's own Accept-Encoding value actually names
+            // gzip/deflate (not merely when the header key is present --
+            // e.g. a caller setting `Accept-Encoding: identity` for unrelated
+            // reasons shouldn't get silently auto-decompressed).
+            let accepts_encoding = req
+                .headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("Accept-Encoding"))
+                .map(|(_, v)| v.to_ascii_lowercase())
+                .is_some_and(|v| v.contains("gzip") || v.contains("deflate"));
+            let content_encoding = headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("Content-Encoding"))
+                .map(|(_, v)| v.trim().to_ascii_lowercase());
+
+            // Only the decompressed branches actually diverge from raw_body,
+            // so only they need a second copy; otherwise `raw_body` moves
+            // straight into `body` and `HttpResponse::raw_body` falls back to
+            // it instead of storing an identical second buffer.
+            let (body, raw_body) = if accepts_encoding {
+                match content_encoding.as_deref() {
+                    Some("gzip") => {
+                        let decoded = gunzip(&raw_body)?;
+                        headers.retain(|k, _| !k.eq_ignore_ascii_case("Content-Encoding"));
+                        (decoded, Some(raw_body))
+                    }
+                    Some("deflate") => {
+                        let decoded = inflate_deflate(&raw_body)?;
+                        headers.retain(|k, _| !k.eq_ignore_ascii_case("Content-Encoding"));
+                        (decoded, Some(raw_body))
+                    }
+                    _ => (raw_body, None),
+                }
+            } else {
+                (raw_body, None)
+            };
 
             // Build final response
             let response = HttpResponse {
                 status_code: response_raw.status_code,
-                headers: response_raw.headers,
+                headers,
                 body,
                 error: response_raw.error.clone(),
+                raw_body,
             };
 
             // Check for error in response
@@ -294,4 +1035,319 @@ impl Http {
     pub fn delete(url: &str) -> Result<HttpResponse> {
         Self::request(HttpRequest::delete(url))
     }
+
+    /// Perform an HTTP request, retrying on transient failures per `policy`.
+    ///
+    /// Only a host-level failure (the host import returning its failure
+    /// sentinel) or a status code in `policy.retry_on` triggers a retry after
+    /// an exponential backoff delay, up to `policy.max_retries` attempts.
+    /// Other errors (malformed response JSON, a decompression failure, an
+    /// app-level error message from the host, ...) are deterministic and are
+    /// returned immediately rather than burning the retry budget on them.
+    pub fn request_with_retry(req: HttpRequest, policy: RetryPolicy) -> Result<HttpResponse> {
+        let mut attempt = 0;
+        loop {
+            let (result, retryable) = match Self::request_raw(&req)? {
+                RawOutcome::HostFailure => (
+                    Err(Error::Other("HTTP request failed".to_string())),
+                    true,
+                ),
+                RawOutcome::Response(response_ptr, response_size) => {
+                    let result = Self::build_response(&req, response_ptr, response_size);
+                    let retryable = matches!(
+                        &result,
+                        Ok(response) if policy.retry_on.contains(&response.status_code)
+                    );
+                    (result, retryable)
+                }
+            };
+
+            if !retryable || attempt >= policy.max_retries {
+                return result;
+            }
+
+            let multiplier = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+            let delay = policy
+                .base_delay_ms
+                .saturating_mul(multiplier)
+                .min(policy.max_delay_ms);
+            unsafe {
+                host_sleep_ms(delay);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Perform an HTTP request with [`RetryPolicy::for_method`]'s default for
+    /// `req.method`: retries enabled for `GET`/`PUT`/`DELETE`, disabled for
+    /// `POST` since it isn't idempotent.
+    pub fn request_auto_retry(req: HttpRequest) -> Result<HttpResponse> {
+        let policy = RetryPolicy::for_method(&req.method);
+        Self::request_with_retry(req, policy)
+    }
+}
+
+/// Retry policy for transient HTTP failures, used with
+/// [`Http::request_with_retry`].
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay_ms: u32,
+    pub max_delay_ms: u32,
+    pub retry_on: Vec<i32>,
+}
+
+impl Default for RetryPolicy {
+    /// Retries up to 3 times on 502/503/504, starting at 200ms and doubling
+    /// up to a 5s cap.
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 200,
+            max_delay_ms: 5_000,
+            retry_on: vec![502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// [`RetryPolicy::default`], for opting an otherwise-disabled method (like
+    /// `POST`) into retries.
+    pub fn enabled() -> Self {
+        Self::default()
+    }
+
+    /// No retries.
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The default policy for `method`: disabled for `POST` unless the caller
+    /// opts in explicitly, enabled for every other method (`GET`, `PUT`,
+    /// `DELETE`, and also methods such as `PATCH`).
+    pub fn for_method(method: &str) -> Self {
+        match method.to_ascii_uppercase().as_str() {
+            "POST" => Self::disabled(),
+            _ => Self::enabled(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "Hello, World! Hello, World! Hello, World!", deflate-compressed with a
+    // fixed-Huffman block (zlib/gzip level 9).
+    const FIXED_HUFFMAN_PLAIN: &[u8] = b"Hello, World! Hello, World! Hello, World!";
+    const FIXED_HUFFMAN_RAW_DEFLATE: &[u8] = &[
+        243, 72, 205, 201, 201, 215, 81, 8, 207, 47, 202, 73, 81, 84, 240, 192, 205, 3, 0,
+    ];
+    const FIXED_HUFFMAN_ZLIB: &[u8] = &[
+        120, 218, 243, 72, 205, 201, 201, 215, 81, 8, 207, 47, 202, 73, 81, 84, 240, 192, 205, 3,
+        0, 29, 101, 13, 124,
+    ];
+    const FIXED_HUFFMAN_GZIP: &[u8] = &[
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 255, 243, 72, 205, 201, 201, 215, 81, 8, 207, 47, 202, 73,
+        81, 84, 240, 192, 205, 3, 0, 204, 98, 131, 118, 41, 0, 0, 0,
+    ];
+
+    // Pangrams repeated a few times, deflate-compressed with a
+    // dynamic-Huffman block (zlib level 9).
+    const DYNAMIC_HUFFMAN_PLAIN: &[u8] = b"the quick brown fox jumps over the lazy dog. \
+the quick brown fox jumps over the lazy dog. \
+the quick brown fox jumps over the lazy dog. \
+pack my box with five dozen liquor jugs. \
+pack my box with five dozen liquor jugs. \
+pack my box with five dozen liquor jugs. ";
+    const DYNAMIC_HUFFMAN_ZLIB: &[u8] = &[
+        120, 218, 181, 203, 219, 17, 128, 32, 16, 67, 209, 86, 82, 129, 61, 129, 2, 174, 10, 11,
+        200, 67, 172, 222, 29, 123, 224, 51, 115, 79, 202, 110, 144, 42, 173, 39, 116, 230, 30,
+        96, 249, 193, 81, 125, 188, 193, 205, 100, 20, 201, 151, 122, 7, 54, 118, 203, 191, 230,
+        224, 168, 196, 249, 1, 45, 168, 83, 217, 97, 169, 25, 73, 175, 9, 184, 40, 85, 206, 242,
+        117, 247, 12, 248, 1, 101, 5, 93, 91,
+    ];
+
+    #[test]
+    fn inflate_decodes_fixed_huffman_block() {
+        assert_eq!(inflate(FIXED_HUFFMAN_RAW_DEFLATE).unwrap(), FIXED_HUFFMAN_PLAIN);
+    }
+
+    #[test]
+    fn inflate_decodes_stored_block() {
+        let mut stream = vec![0x01]; // final bit set, block type 00 (stored)
+        let data: &[u8] = b"a stored block round-trips unchanged";
+        stream.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        stream.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+        stream.extend_from_slice(data);
+
+        assert_eq!(inflate(&stream).unwrap(), data);
+    }
+
+    #[test]
+    fn gunzip_round_trips_known_good_gzip_output() {
+        assert_eq!(gunzip(FIXED_HUFFMAN_GZIP).unwrap(), FIXED_HUFFMAN_PLAIN);
+    }
+
+    #[test]
+    fn gunzip_rejects_truncated_header() {
+        // Valid 10-byte fixed header (FEXTRA set) + an extra_len field that
+        // claims a 1000-byte FEXTRA field the buffer doesn't actually have,
+        // padded to the 18-byte minimum so the check that the header end
+        // lies inside the buffer is what has to catch this, not the trivial
+        // "too short to be gzip at all" check.
+        let mut truncated = vec![0x1f, 0x8b, 0x08, 0x04, 0, 0, 0, 0, 0, 0xff];
+        truncated.extend_from_slice(&1000u16.to_le_bytes());
+        truncated.extend_from_slice(&[0; 6]);
+        assert_eq!(truncated.len(), 18);
+        assert!(gunzip(&truncated).is_err());
+    }
+
+    #[test]
+    fn zlib_inflate_round_trips_fixed_and_dynamic_huffman() {
+        assert_eq!(zlib_inflate(FIXED_HUFFMAN_ZLIB).unwrap(), FIXED_HUFFMAN_PLAIN);
+        assert_eq!(
+            zlib_inflate(DYNAMIC_HUFFMAN_ZLIB).unwrap(),
+            DYNAMIC_HUFFMAN_PLAIN
+        );
+    }
+
+    #[test]
+    fn inflate_deflate_unwraps_zlib_when_present() {
+        assert_eq!(inflate_deflate(FIXED_HUFFMAN_ZLIB).unwrap(), FIXED_HUFFMAN_PLAIN);
+    }
+
+    #[test]
+    fn inflate_deflate_falls_back_to_raw_deflate() {
+        // No zlib header here, just the bare DEFLATE bitstream.
+        assert_eq!(
+            inflate_deflate(FIXED_HUFFMAN_RAW_DEFLATE).unwrap(),
+            FIXED_HUFFMAN_PLAIN
+        );
+    }
+
+    #[test]
+    fn is_zlib_header_requires_full_mod31_validity() {
+        // A real zlib header (CMF=0x78, FLG=0xDA): CM=8 and mod-31 holds.
+        assert!(is_zlib_header(0x78, 0xda));
+        // CM nibble is 8 (the weak check this replaced would accept it), but
+        // the 16-bit header isn't a multiple of 31, so a real zlib stream
+        // would never produce it -- this is exactly the kind of raw-deflate
+        // first byte that used to be misidentified as zlib-wrapped.
+        assert!(!is_zlib_header(0x08, 0x00));
+    }
+
+    #[test]
+    fn inflate_rejects_output_over_the_decompression_bomb_cap() {
+        // Chain enough stored blocks (each capped at 65535 bytes by the
+        // format) to exceed MAX_INFLATE_OUTPUT_SIZE without ever reaching a
+        // final block.
+        let block_len: usize = 65535;
+        let blocks_needed = MAX_INFLATE_OUTPUT_SIZE / block_len + 1;
+        let mut stream = Vec::with_capacity(blocks_needed * (block_len + 5));
+        let payload = vec![0u8; block_len];
+        for _ in 0..blocks_needed {
+            stream.push(0x00); // final=0, type=00 (stored)
+            stream.extend_from_slice(&(block_len as u16).to_le_bytes());
+            stream.extend_from_slice(&(!(block_len as u16)).to_le_bytes());
+            stream.extend_from_slice(&payload);
+        }
+
+        match inflate(&stream) {
+            Err(Error::Other(msg)) => assert!(msg.contains("too large")),
+            other => panic!("expected a decompression-bomb error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn normalize_charset_label_recognizes_aliases_and_falls_back_to_utf8() {
+        assert_eq!(normalize_charset_label("ISO-8859-1"), "iso-8859-1");
+        assert_eq!(normalize_charset_label(" latin1 "), "iso-8859-1");
+        assert_eq!(normalize_charset_label("CP1252"), "windows-1252");
+        assert_eq!(normalize_charset_label("UTF-8"), "utf-8");
+        assert_eq!(normalize_charset_label("gbk"), "utf-8");
+    }
+
+    #[test]
+    fn decode_iso_8859_1_maps_every_byte_to_the_identical_code_point() {
+        let bytes: Vec<u8> = (0u8..=255).collect();
+        let expected: String = bytes.iter().map(|&b| b as char).collect();
+        assert_eq!(decode_iso_8859_1(&bytes), expected);
+    }
+
+    #[test]
+    fn decode_windows_1252_remaps_the_c1_control_range() {
+        // 0x80 -> EURO SIGN, 0x95 -> BULLET, 0x9F -> LATIN CAPITAL LETTER Y
+        // WITH DIAERESIS; bytes outside 0x80-0x9F are unchanged from Latin-1.
+        assert_eq!(decode_windows_1252(&[0x80]), "\u{20AC}");
+        assert_eq!(decode_windows_1252(&[0x95]), "\u{2022}");
+        assert_eq!(decode_windows_1252(&[0x9F]), "\u{0178}");
+        assert_eq!(decode_windows_1252(&[0x41]), "A");
+        assert_eq!(decode_windows_1252(&[0xE9]), "\u{00E9}");
+    }
+
+    #[test]
+    fn decode_charset_errors_on_invalid_utf8_but_not_for_single_byte_charsets() {
+        let invalid_utf8 = [0xFF, 0xFE];
+        assert!(decode_charset(&invalid_utf8, "utf-8").is_err());
+        assert_eq!(decode_charset(&invalid_utf8, "iso-8859-1").unwrap(), "\u{00FF}\u{00FE}");
+    }
+
+    #[test]
+    fn decode_charset_lossy_replaces_invalid_utf8_instead_of_erroring() {
+        let invalid_utf8 = [0xFF, 0xFE];
+        assert_eq!(decode_charset_lossy(&invalid_utf8, "utf-8"), "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn base64_encode_matches_known_vectors() {
+        // RFC 4648 test vectors.
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foob"), "Zm9vYg==");
+        assert_eq!(base64_encode(b"fooba"), "Zm9vYmE=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn base64_encode_round_trips_through_base64_decode() {
+        let data: Vec<u8> = (0u8..=255).collect();
+        for len in [0, 1, 2, 3, 4, 61, 62, 63, 256] {
+            let chunk = &data[..len.min(data.len())];
+            assert_eq!(base64_decode(&base64_encode(chunk)).unwrap(), chunk);
+        }
+    }
+
+    #[test]
+    fn percent_encode_leaves_unreserved_characters_untouched() {
+        assert_eq!(
+            percent_encode("ABCxyz019-._~"),
+            "ABCxyz019-._~"
+        );
+    }
+
+    #[test]
+    fn percent_encode_escapes_everything_else_including_literal_percent_signs() {
+        assert_eq!(percent_encode(" "), "%20");
+        assert_eq!(percent_encode("a b&c=d"), "a%20b%26c%3Dd");
+        // Input that already looks percent-encoded is escaped again, not
+        // passed through -- the `%` itself isn't in the unreserved set.
+        assert_eq!(percent_encode("100%done"), "100%25done");
+        assert_eq!(percent_encode("%20"), "%2520");
+    }
+
+    #[test]
+    fn encode_pairs_joins_percent_encoded_key_value_pairs_with_ampersands() {
+        assert_eq!(encode_pairs(&[]), "");
+        assert_eq!(encode_pairs(&[("q", "rust lang")]), "q=rust%20lang");
+        assert_eq!(
+            encode_pairs(&[("a", "1"), ("b c", "2&3")]),
+            "a=1&b%20c=2%263"
+        );
+    }
 }